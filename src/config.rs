@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+use serde::Deserialize;
+
+const DEFAULT_WORK_MINUTES: u32 = 25;
+const DEFAULT_SHORT_BREAK_MINUTES: u32 = 5;
+const DEFAULT_LONG_BREAK_MINUTES: u32 = 30;
+
+/// User-configurable durations, loaded from `settings.toml` in the platform
+/// config directory. Missing fields fall back to the classic 25/5/30 cadence.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub work_time: u32,
+    pub short_break: u32,
+    pub long_break: u32,
+    /// Custom sound to play when a timer completes. Falls back to the
+    /// built-in chime when unset.
+    pub sound_file: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            work_time: DEFAULT_WORK_MINUTES,
+            short_break: DEFAULT_SHORT_BREAK_MINUTES,
+            long_break: DEFAULT_LONG_BREAK_MINUTES,
+            sound_file: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads `settings.toml` from the platform config directory, falling back
+    /// to defaults when the directory or file doesn't exist. A malformed file
+    /// also falls back to defaults, but logs the parse error to stderr first.
+    pub fn load() -> Config {
+        match settings_path() {
+            Some(path) => match fs::read_to_string(&path) {
+                Ok(contents) => toml::from_str(&contents).unwrap_or_else(|err| {
+                    eprintln!("settings.toml is malformed, using defaults: {}", err);
+                    Config::default()
+                }),
+                Err(_) => Config::default(),
+            },
+            None => Config::default(),
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "pomodoro")?;
+    Some(dirs.config_dir().join("settings.toml"))
+}
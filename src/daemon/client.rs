@@ -0,0 +1,27 @@
+use std::error::Error;
+use std::os::unix::net::UnixStream;
+
+use super::{socket_path, Answer, Command};
+
+/// Connects to the running daemon, sends one `Command`, and prints its reply.
+pub(super) fn send(command: Command) -> Result<(), Box<dyn Error>> {
+    let stream = UnixStream::connect(socket_path())?;
+    serde_cbor::to_writer(&stream, &command)?;
+
+    let answer: Answer = serde_cbor::from_reader(&stream)?;
+    match answer {
+        Answer::Ok => println!("ok"),
+        Answer::Status {
+            state,
+            order,
+            remaining,
+        } => {
+            println!("state: {}", state);
+            println!("cycle: {}", order.map_or("-".to_string(), |n| n.to_string()));
+            println!("remaining: {}", remaining);
+        }
+        Answer::Error(message) => eprintln!("daemon error: {}", message),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! A background daemon that owns a single, anonymous pomodoro session and
+//! serves it over a control socket, so the CLI can be a thin client. There's
+//! one session per daemon instance, not named or multiple concurrent timers.
+
+mod client;
+mod server;
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::config::Config;
+
+#[derive(StructOpt, Debug)]
+pub enum DaemonCommand {
+    #[structopt(name = "start")]
+    /// Starts the daemon and begins a pomodoro session
+    Start,
+
+    #[structopt(name = "toggle")]
+    /// Pauses or resumes the pomodoro the daemon is running
+    Toggle,
+
+    #[structopt(name = "list")]
+    /// Reports the current state, cycle order, and remaining time
+    List,
+
+    #[structopt(name = "stop")]
+    /// Stops the daemon
+    Stop,
+}
+
+/// A request sent from the client to the daemon over the control socket.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Command {
+    Toggle,
+    List,
+    Stop,
+}
+
+/// The daemon's reply to a `Command`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Answer {
+    Ok,
+    Status {
+        state: String,
+        order: Option<i32>,
+        remaining: String,
+    },
+    Error(String),
+}
+
+pub fn handle(command: DaemonCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        DaemonCommand::Start => server::run(Config::load()),
+        DaemonCommand::Toggle => client::send(Command::Toggle),
+        DaemonCommand::List => client::send(Command::List),
+        DaemonCommand::Stop => client::send(Command::Stop),
+    }
+}
+
+/// Path of the Unix domain socket the daemon listens on.
+fn socket_path() -> PathBuf {
+    match ProjectDirs::from("", "", "pomodoro") {
+        Some(dirs) => dirs.cache_dir().join("daemon.sock"),
+        None => PathBuf::from("/tmp/pomodoro-daemon.sock"),
+    }
+}
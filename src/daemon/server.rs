@@ -0,0 +1,154 @@
+use std::error::Error;
+use std::fs;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::notifications;
+use crate::{Clock, PomodoroState, StateTracker};
+
+use super::{socket_path, Answer, Command};
+
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The pomodoro session the daemon owns, ticked in the background and
+/// reported on / controlled through the control socket.
+struct Session {
+    tracker: StateTracker,
+    target: Duration,
+    elapsed: Duration,
+    paused: bool,
+}
+
+impl Session {
+    fn new(config: Config) -> Session {
+        let mut session = Session {
+            tracker: StateTracker::new(config),
+            target: Duration::new(0, 0),
+            elapsed: Duration::new(0, 0),
+            paused: false,
+        };
+        session.begin_work();
+        session
+    }
+
+    fn begin_work(&mut self) {
+        self.tracker.current_state = PomodoroState::Working;
+        self.tracker.increment_cycle();
+        self.target = Duration::from_millis(u64::from(self.tracker.work_time_ms()));
+        self.elapsed = Duration::new(0, 0);
+        notifications::notify_phase(&PomodoroState::Working);
+    }
+
+    fn begin_break(&mut self) {
+        self.tracker.set_break();
+        let ms = match self.tracker.current_state {
+            PomodoroState::ShortBreak => self.tracker.short_break_ms(),
+            PomodoroState::LongBreak => self.tracker.long_break_ms(),
+            PomodoroState::Working | PomodoroState::None => 0,
+        };
+        self.target = Duration::from_millis(u64::from(ms));
+        self.elapsed = Duration::new(0, 0);
+        notifications::notify_phase(&self.tracker.current_state);
+    }
+
+    fn remaining(&self) -> Duration {
+        self.target.checked_sub(self.elapsed).unwrap_or_default()
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        if self.paused {
+            return;
+        }
+
+        self.elapsed += delta;
+        if self.remaining().is_zero() {
+            match self.tracker.current_state {
+                PomodoroState::Working => self.begin_break(),
+                PomodoroState::ShortBreak | PomodoroState::LongBreak | PomodoroState::None => {
+                    self.begin_work()
+                }
+            }
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    fn status(&self) -> Answer {
+        let mut clock = Clock::new();
+        clock.set_time_ms(self.remaining().as_millis() as u32);
+        Answer::Status {
+            state: format!("{:?}", self.tracker.current_state),
+            order: self.tracker.get_order(),
+            remaining: clock.get_time(),
+        }
+    }
+}
+
+/// Runs the daemon in the foreground: binds the control socket, ticks the
+/// session in a background thread, and serves one `Command` per connection.
+pub(super) fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    let path = socket_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let _ = fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)?;
+    let session = Arc::new(Mutex::new(Session::new(config)));
+
+    {
+        let session = Arc::clone(&session);
+        thread::spawn(move || loop {
+            thread::sleep(TICK_INTERVAL);
+            session.lock().unwrap().tick(TICK_INTERVAL);
+        });
+    }
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("daemon: failed to accept connection: {}", err);
+                continue;
+            }
+        };
+
+        match handle_connection(stream, &session) {
+            Ok(keep_running) => {
+                if !keep_running {
+                    break;
+                }
+            }
+            Err(err) => eprintln!("daemon: failed to handle connection: {}", err),
+        }
+    }
+
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+/// Handles one client connection, returning whether the daemon should keep
+/// running afterwards (`false` once a `Stop` command is served).
+fn handle_connection(
+    stream: UnixStream,
+    session: &Arc<Mutex<Session>>,
+) -> Result<bool, Box<dyn Error>> {
+    let command: Command = serde_cbor::from_reader(&stream)?;
+
+    let (answer, keep_running) = match command {
+        Command::Toggle => {
+            session.lock().unwrap().toggle_pause();
+            (Answer::Ok, true)
+        }
+        Command::List => (session.lock().unwrap().status(), true),
+        Command::Stop => (Answer::Ok, false),
+    };
+
+    serde_cbor::to_writer(&stream, &answer)?;
+    Ok(keep_running)
+}
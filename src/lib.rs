@@ -1,27 +1,94 @@
-#[macro_use]
-extern crate structopt;
+mod config;
+mod daemon;
+mod notifications;
+mod sound;
 
-use std::io::{self, Write};
+use std::io::{self, BufReader, Bytes, Read, Write};
 use std::thread::sleep;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use std::error::Error;
-use termion::{clear, cursor};
+use termion::raw::IntoRawMode;
+use termion::{async_stdin, clear, cursor, AsyncReader};
 
 use structopt::StructOpt;
 
+pub use config::Config;
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = "pomodoro", about = "a rust based pomodoro timer")]
 /// You can use this terminal program to start a pomodoro timer
 pub enum PomodoroConfig {
     #[structopt(name = "start")]
     /// Starts your pomodoro timer
-    Start,
+    Start {
+        /// Overrides the work duration for this session, e.g. "25m" or "1h30m"
+        #[structopt(long)]
+        work: Option<String>,
+
+        /// Overrides the short break duration for this session, e.g. "5m"
+        #[structopt(long = "short-break")]
+        short_break: Option<String>,
+
+        /// Overrides the long break duration for this session, e.g. "30m"
+        #[structopt(long = "long-break")]
+        long_break: Option<String>,
+
+        /// Disables the completion sound for this session
+        #[structopt(long = "no-sound")]
+        no_sound: bool,
+    },
+
+    #[structopt(name = "daemon")]
+    /// Runs or controls the background pomodoro daemon
+    Daemon {
+        #[structopt(subcommand)]
+        command: daemon::DaemonCommand,
+    },
+}
+
+/// Session-scoped overrides parsed from the CLI, taking precedence over
+/// whatever `Config` loaded from `settings.toml` or its defaults.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    work: Option<Duration>,
+    short_break: Option<Duration>,
+    long_break: Option<Duration>,
+    no_sound: bool,
 }
 
-pub fn run(config: PomodoroConfig) -> Result<(), Box<dyn Error>> {
-    match config {
-        PomodoroConfig::Start => start_pomodoro(),
+impl Overrides {
+    fn from_args(
+        work: Option<String>,
+        short_break: Option<String>,
+        long_break: Option<String>,
+        no_sound: bool,
+    ) -> Result<Overrides, humantime::DurationError> {
+        Ok(Overrides {
+            work: parse_duration_arg(work)?,
+            short_break: parse_duration_arg(short_break)?,
+            long_break: parse_duration_arg(long_break)?,
+            no_sound,
+        })
+    }
+}
+
+fn parse_duration_arg(arg: Option<String>) -> Result<Option<Duration>, humantime::DurationError> {
+    arg.map(|raw| humantime::parse_duration(&raw)).transpose()
+}
+
+pub fn run(cli_config: PomodoroConfig) -> Result<(), Box<dyn Error>> {
+    match cli_config {
+        PomodoroConfig::Start {
+            work,
+            short_break,
+            long_break,
+            no_sound,
+        } => {
+            let overrides = Overrides::from_args(work, short_break, long_break, no_sound)?;
+            start_pomodoro(Config::load(), overrides)
+        }
+        PomodoroConfig::Daemon { command } => return daemon::handle(command),
     }
 
     Ok(())
@@ -29,20 +96,55 @@ pub fn run(config: PomodoroConfig) -> Result<(), Box<dyn Error>> {
 
 #[derive(Debug)]
 pub struct StateTracker {
+    config: Config,
+    overrides: Overrides,
     current_order: Option<i32>,
     current_state: PomodoroState,
     started_at: Option<SystemTime>,
 }
 
 impl StateTracker {
-    pub fn new() -> StateTracker {
+    pub fn new(config: Config) -> StateTracker {
+        StateTracker::with_overrides(config, Overrides::default())
+    }
+
+    pub fn with_overrides(config: Config, overrides: Overrides) -> StateTracker {
         StateTracker {
+            config,
+            overrides,
             current_order: None,
             current_state: PomodoroState::None,
             started_at: None,
         }
     }
 
+    fn work_time_ms(&self) -> u32 {
+        match self.overrides.work {
+            Some(duration) => duration.as_millis() as u32,
+            None => self.config.work_time * 60000,
+        }
+    }
+
+    fn short_break_ms(&self) -> u32 {
+        match self.overrides.short_break {
+            Some(duration) => duration.as_millis() as u32,
+            None => self.config.short_break * 60000,
+        }
+    }
+
+    fn long_break_ms(&self) -> u32 {
+        match self.overrides.long_break {
+            Some(duration) => duration.as_millis() as u32,
+            None => self.config.long_break * 60000,
+        }
+    }
+
+    fn maybe_play_chime(&self, outcome: &CountdownOutcome) {
+        if *outcome == CountdownOutcome::Completed && !self.overrides.no_sound {
+            sound::play_completion_chime(self.config.sound_file.clone());
+        }
+    }
+
     fn increment_cycle(&mut self) {
         let new_order = match self.current_order {
             Some(num) if num < 4 => Some(num + 1),
@@ -60,37 +162,54 @@ impl StateTracker {
         self.current_order
     }
 
-    pub fn start_work(&mut self) {
+    pub fn start_work(&mut self, keys: &mut Option<Keys>) -> CountdownOutcome {
         let now = SystemTime::now();
         self.started_at = Some(now);
 
         let mut clock = Clock::new();
         self.current_state = PomodoroState::Working;
         self.increment_cycle();
-        clock.set_time_minutes(25);
-        clock.countdown();
-        self.set_break();
-        self.start_break();
+        clock.set_time_ms(self.work_time_ms());
+        let outcome = clock.countdown(keys);
+        self.maybe_play_chime(&outcome);
+        match outcome {
+            CountdownOutcome::Quit => CountdownOutcome::Quit,
+            CountdownOutcome::Completed | CountdownOutcome::Skipped => {
+                self.set_break();
+                notifications::notify_phase(&self.current_state);
+                self.start_break(keys)
+            }
+        }
     }
 
-    pub fn start_break(&mut self) {
-        match self.current_state {
-            PomodoroState::ShortBreak => self.short_break(),
-            PomodoroState::LongBreak => self.long_break(),
-            _ => (),
+    pub fn start_break(&mut self, keys: &mut Option<Keys>) -> CountdownOutcome {
+        let outcome = match self.current_state {
+            PomodoroState::ShortBreak => self.short_break(keys),
+            PomodoroState::LongBreak => self.long_break(keys),
+            _ => return CountdownOutcome::Completed,
+        };
+
+        if outcome != CountdownOutcome::Quit {
+            notifications::notify_phase(&PomodoroState::Working);
         }
+
+        outcome
     }
 
-    pub fn short_break(&mut self) {
+    pub fn short_break(&mut self, keys: &mut Option<Keys>) -> CountdownOutcome {
         let mut clock = Clock::new();
-        clock.set_time_minutes(5);
-        clock.countdown();
+        clock.set_time_ms(self.short_break_ms());
+        let outcome = clock.countdown(keys);
+        self.maybe_play_chime(&outcome);
+        outcome
     }
 
-    pub fn long_break(&mut self) {
+    pub fn long_break(&mut self, keys: &mut Option<Keys>) -> CountdownOutcome {
         let mut clock = Clock::new();
-        clock.set_time_minutes(30);
-        clock.countdown();
+        clock.set_time_ms(self.long_break_ms());
+        let outcome = clock.countdown(keys);
+        self.maybe_play_chime(&outcome);
+        outcome
     }
 
     pub fn set_break(&mut self) {
@@ -98,64 +217,119 @@ impl StateTracker {
             Some(_x @ 0..=3) => PomodoroState::ShortBreak,
             Some(_x @ 4) => PomodoroState::LongBreak,
             Some(_) => PomodoroState::None,
-            None => PomodoroState::None,
+            // increment_cycle() wraps back to None after the 4th session, which
+            // marks the start of a new round rather than "no active cycle".
+            None => PomodoroState::ShortBreak,
         };
 
         self.current_state = break_state;
     }
 }
 
-fn start_pomodoro() {
-    let mut pomodoro_tracker = StateTracker::new();
-    pomodoro_tracker.start_work();
+fn start_pomodoro(config: Config, overrides: Overrides) {
+    let mut pomodoro_tracker = StateTracker::with_overrides(config, overrides);
+
+    // Held for the whole session rather than re-created per countdown: a
+    // fresh async_stdin() on every call left the previous call's background
+    // reader thread still blocked on a tty read, racing the next prompt for
+    // the user's next keystroke.
+    let raw_mode = io::stdout().into_raw_mode().ok();
+    let mut keys = raw_mode.as_ref().map(|_| BufReader::new(async_stdin()).bytes());
+
+    loop {
+        if let CountdownOutcome::Quit = pomodoro_tracker.start_work(&mut keys) {
+            break;
+        }
+
+        if !prompt_continue(&mut keys) {
+            pomodoro_tracker.restart_cycle();
+            break;
+        }
+    }
+}
+
+/// Asks the user whether to keep cycling after a work+break pair finishes.
+/// When interactive, reads a single key from the same tty stream `countdown`
+/// uses, rather than a separate blocking stdin read that could race it for
+/// the next keystroke. Off-TTY, falls back to a plain line read.
+fn prompt_continue(keys: &mut Option<Keys>) -> bool {
+    print!("\r\nContinue the cycle? [y/n] ");
+    io::stdout().flush().unwrap();
+
+    match keys.as_mut() {
+        Some(reader) => loop {
+            match reader.next() {
+                Some(Ok(b'y')) | Some(Ok(b'Y')) => break true,
+                Some(Ok(b'n')) | Some(Ok(b'N')) => break false,
+                Some(_) => continue,
+                None => sleep(Duration::from_millis(100)),
+            }
+        },
+        None => {
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input).is_err() {
+                return false;
+            }
+
+            matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+        }
+    }
+}
+
+/// The way a `Clock::countdown` ended, so `StateTracker` can decide whether
+/// to keep moving through the cycle or stop.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CountdownOutcome {
+    Completed,
+    Skipped,
+    Quit,
 }
 
 #[derive(Debug)]
-enum PomodoroState {
+pub(crate) enum PomodoroState {
     Working,
     ShortBreak,
     LongBreak,
     None,
 }
 
+/// A key-press stream read from the tty in a background thread. `None` when
+/// stdout isn't a tty, so there's nothing to read interactively.
+type Keys = Bytes<BufReader<AsyncReader>>;
+
 struct Clock {
-    minutes: u32,
-    seconds: u32,
+    total_ms: u32,
 }
 
 impl Clock {
     pub fn new() -> Clock {
-        Clock {
-            minutes: 0,
-            seconds: 0,
-        }
+        Clock { total_ms: 0 }
     }
 
     pub fn set_time_ms(&mut self, ms: u32) {
-        self.minutes = (ms / (1000 * 60)) % 60;
-        self.seconds = (ms / 1000) % 60;
+        self.total_ms = ms;
     }
 
-    pub fn set_time_minutes(&mut self, minutes: u32) {
-        self.set_time_ms(minutes * 60000);
+    pub fn get_ms_from_time(&self) -> u32 {
+        self.total_ms
     }
 
-    pub fn decrement_one_second(&mut self) {
-        let mut time_in_ms = self.get_ms_from_time();
-        time_in_ms -= 1000;
-        self.set_time_ms(time_in_ms);
+    pub fn get_time(&self) -> String {
+        format!("{:02}:{:02}", self.minutes(), self.seconds())
     }
 
-    pub fn get_ms_from_time(&mut self) -> u32 {
-        (self.minutes * 60000) + (self.seconds * 1000)
+    fn minutes(&self) -> u32 {
+        self.total_ms / 60000
     }
 
-    pub fn get_time(&self) -> String {
-        format!("{:02}:{:02}", self.minutes, self.seconds)
+    fn seconds(&self) -> u32 {
+        (self.total_ms / 1000) % 60
     }
 
-    pub fn draw_work_clock(&self) -> () {
-        let (x, y) = termion::terminal_size().unwrap();
+    pub fn draw_work_clock(&self) {
+        // Redirected/piped output has no terminal size to query; fall back to
+        // a sane default rather than panicking on the first draw.
+        let (x, y) = termion::terminal_size().unwrap_or((80, 24));
         let clock = format!("\r\n
 ╭───────────────────────────────────────╮
 │                                       │
@@ -163,7 +337,7 @@ impl Clock {
 │                 {:02}:{:02}                 │
 │                                       │
 ╰───────────────────────────────────────╯
-", self.minutes, self.seconds);
+", self.minutes(), self.seconds());
         print!("{}", clear::All);
         for (i, line) in clock.lines().enumerate() {
             println!(
@@ -175,23 +349,42 @@ impl Clock {
         }
     }
 
-    pub fn countdown(&mut self) {
-        let (x, y) = termion::terminal_size().unwrap();
+    pub fn countdown(&mut self, keys: &mut Option<Keys>) -> CountdownOutcome {
+        const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+        let target = Duration::from_millis(u64::from(self.get_ms_from_time()));
+
+        let mut elapsed = Duration::new(0, 0);
+        let mut paused = false;
+        let mut last_tick = Instant::now();
+
         loop {
-            sleep(Duration::new(1, 0));
-            self.decrement_one_second();
-            self.draw_work_clock();
+            sleep(POLL_INTERVAL);
+
+            if let Some(reader) = keys.as_mut() {
+                if let Some(Ok(key)) = reader.next() {
+                    match key {
+                        b'p' | b' ' => paused = !paused,
+                        b's' => break CountdownOutcome::Skipped,
+                        b'q' => break CountdownOutcome::Quit,
+                        _ => (),
+                    }
+                }
+            }
 
-            // print!(
-            //     "{}{}{}",
-            //     clear::All,
-            //     cursor::Goto(x / 2, y / 2),
-            //     current_clock,
-            // );
+            let now = Instant::now();
+            if !paused {
+                elapsed += now - last_tick;
+            }
+            last_tick = now;
+
+            let remaining = target.checked_sub(elapsed).unwrap_or_default();
+            self.set_time_ms(remaining.as_millis() as u32);
+            self.draw_work_clock();
             io::stdout().flush().unwrap();
 
-            if self.get_ms_from_time() == 0 {
-                break;
+            if remaining.is_zero() {
+                break CountdownOutcome::Completed;
             }
         }
     }
@@ -211,20 +404,29 @@ mod tests {
     #[test]
     fn test_clock_minutes() {
         let mut clock = Clock::new();
-        clock.set_time_minutes(25);
+        clock.set_time_ms(25 * 60000);
         assert_eq!(clock.get_time(), "25:00");
     }
 
+    #[test]
+    fn test_config_driven_duration_above_an_hour_is_not_truncated() {
+        // e.g. `work_time = 90` or `long_break = 90` in settings.toml.
+        let mut clock = Clock::new();
+        clock.set_time_ms(90 * 60000);
+        assert_eq!(clock.get_time(), "90:00");
+        assert_eq!(clock.get_ms_from_time(), 90 * 60000);
+    }
+
     #[test]
     fn test_start_cycle() {
-        let mut pstate = StateTracker::new();
+        let mut pstate = StateTracker::new(Config::default());
         pstate.increment_cycle();
         assert_eq!(pstate.get_order().unwrap(), 1);
     }
 
     #[test]
     fn test_increment_cycle() {
-        let mut pstate = StateTracker::new();
+        let mut pstate = StateTracker::new(Config::default());
         pstate.increment_cycle();
         pstate.increment_cycle();
         assert_eq!(pstate.get_order().unwrap(), 2);
@@ -232,7 +434,7 @@ mod tests {
 
     #[test]
     fn test_cycle_loop() {
-        let mut pstate = StateTracker::new();
+        let mut pstate = StateTracker::new(Config::default());
         pstate.increment_cycle();
         pstate.increment_cycle();
         pstate.increment_cycle();
@@ -243,7 +445,7 @@ mod tests {
 
     #[test]
     fn test_cycle_restart() {
-        let mut pstate = StateTracker::new();
+        let mut pstate = StateTracker::new(Config::default());
         pstate.restart_cycle();
         assert_eq!(pstate.get_order(), None);
     }
@@ -0,0 +1,19 @@
+use notify_rust::Notification;
+
+use crate::PomodoroState;
+
+/// Fires a native desktop notification announcing the `PomodoroState` being
+/// entered. Headless systems (or anything without a notification daemon)
+/// just get a logged warning instead of a crash.
+pub(crate) fn notify_phase(state: &PomodoroState) {
+    let (summary, body) = match state {
+        PomodoroState::Working => ("Time to Work!", "Your break is over, let's get back to it."),
+        PomodoroState::ShortBreak => ("Take a short break", "Step away for a few minutes."),
+        PomodoroState::LongBreak => ("Take a long break", "You've earned a longer rest."),
+        PomodoroState::None => return,
+    };
+
+    if let Err(err) = Notification::new().summary(summary).body(body).show() {
+        eprintln!("could not send desktop notification: {}", err);
+    }
+}
@@ -0,0 +1,43 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use rodio::{Decoder, OutputStream, Sink};
+
+const DEFAULT_CHIME: &[u8] = include_bytes!("../assets/chime.wav");
+
+/// Plays the configured completion sound (or the built-in chime) once, on a
+/// short-lived output stream spawned in its own thread so it doesn't block
+/// the next phase from starting.
+pub(crate) fn play_completion_chime(sound_file: Option<PathBuf>) {
+    thread::spawn(move || {
+        let result = match &sound_file {
+            Some(path) => play_file(path),
+            None => play_bytes(DEFAULT_CHIME),
+        };
+
+        if let Err(err) = result {
+            eprintln!("could not play completion sound: {}", err);
+        }
+    });
+}
+
+fn play_file(path: &Path) -> Result<(), Box<dyn Error>> {
+    let (_stream, handle) = OutputStream::try_default()?;
+    let source = Decoder::new(BufReader::new(File::open(path)?))?;
+    let sink = Sink::try_new(&handle)?;
+    sink.append(source);
+    sink.sleep_until_end();
+    Ok(())
+}
+
+fn play_bytes(bytes: &'static [u8]) -> Result<(), Box<dyn Error>> {
+    let (_stream, handle) = OutputStream::try_default()?;
+    let source = Decoder::new(Cursor::new(bytes))?;
+    let sink = Sink::try_new(&handle)?;
+    sink.append(source);
+    sink.sleep_until_end();
+    Ok(())
+}